@@ -1,104 +1,154 @@
-pub const MAX_DIRS: u64 = (1 << 32) - 1;
+use std::collections::HashMap;
 
-pub const MAX_FILES: u64 = (1 << 32) - 1;
+/// The mount point itself. Reserved.
+pub const ROOT_INO: u64 = 1;
 
-/// A directory inode number.
-///
-/// When converted to a `u64`, the directory number will use the lower 32 bits.
-///
-/// The number `0` is reserved.
-/// The number `1` is used for the mount point.
-#[derive(Copy, Clone, Debug)]
-pub struct DirInode(u32);
+/// The single `hello.txt` file shared by every directory. Reserved.
+pub const HELLO_INO: u64 = 2;
 
-/// A file inode number.
-///
-/// When converted to a `u64`, the value will contain its directory inode number
-/// in the lower 32 bits, and the file number in the upper 32 bits.
-///
-/// The number `0` is reserved for the parent directory itself.
-///
-#[derive(Copy, Clone, Debug)]
-pub struct FileInode(DirInode, u32);
-
-pub enum Inode {
-    Dir(DirInode),
-    File(FileInode),
+/// Which symlink-loop entry an allocated symlink inode represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkKind {
+    /// Points at a sibling `pitNNN` directory.
+    Next,
+    /// Points back up toward the root.
+    Up,
 }
 
-impl From<DirInode> for Inode {
-    fn from(dir_inode: DirInode) -> Self {
-        Self::Dir(dir_inode)
-    }
+/// What kind of entry a dynamically-allocated inode refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Dir,
+    Symlink(SymlinkKind),
 }
 
-impl From<FileInode> for Inode {
-    fn from(file_inode: FileInode) -> Self {
-        Self::File(file_inode)
-    }
+/// One allocated inode: where it hangs in the tree, how deep, and what it is.
+#[derive(Debug, Clone)]
+struct Entry {
+    parent: u64,
+    depth: u32,
+    kind: EntryKind,
 }
 
-impl From<DirInode> for u64 {
-    fn from(inode: DirInode) -> Self {
-        inode.0 as u64
-    }
+/// Lazily allocates inode numbers for the recursive `pitNNN` directory tree
+/// and the symlinks planted inside it.
+///
+/// The tree has no fixed depth, so inodes can't be computed from a directory
+/// number the way they used to be; instead each `(parent, name)` pair is
+/// handed a fresh inode the first time it's looked up, and the mapping is
+/// cached in both directions so repeat visits (and `..`) stay stable for the
+/// lifetime of the mount.
+#[derive(Debug)]
+pub struct InodeTable {
+    next_ino: u64,
+    forward: HashMap<(u64, String), u64>,
+    reverse: HashMap<u64, Entry>,
 }
 
-impl From<FileInode> for u64 {
-    fn from(inode: FileInode) -> Self {
-        // Lower 32 bits identify the directory; upper 32 bits identify the file.
-        let dir_num = inode.0.0;
-        let file_num = inode.1;
-        ((file_num as u64) << 32) | (dir_num as u64)
+impl Default for InodeTable {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl From<Inode> for u64 {
-    fn from(inode: Inode) -> Self {
-        match inode {
-            Inode::Dir(dir_inode) => dir_inode.into(),
-            Inode::File(file_inode) => file_inode.into(),
+impl InodeTable {
+    pub fn new() -> Self {
+        Self {
+            // Inode numbers below this are reserved (0 is unused, 1 is the
+            // root, 2 is hello.txt).
+            next_ino: HELLO_INO + 1,
+            forward: HashMap::new(),
+            reverse: HashMap::new(),
         }
     }
-}
 
-impl DirInode {
-    pub fn from_number(num: u64) -> Option<Self> {
-        if num > MAX_DIRS {
-            None
-        } else {
-            Some(Self(num.try_into().unwrap()))
+    /// Returns the inode for `parent`'s child `name`, allocating one of kind
+    /// `kind` if this is the first time it's been visited.
+    pub fn lookup_or_insert(&mut self, parent: u64, name: &str, kind: EntryKind) -> u64 {
+        if let Some(&ino) = self.forward.get(&(parent, name.to_owned())) {
+            return ino;
         }
+
+        let ino = self.next_ino;
+        self.next_ino += 1;
+        let depth = self.depth(parent) + 1;
+        self.forward.insert((parent, name.to_owned()), ino);
+        self.reverse.insert(ino, Entry { parent, depth, kind });
+        ino
     }
 
-    pub fn num(&self) -> u64 {
-        self.0.into()
+    /// The kind of entry `ino` refers to, if it's a known, non-root inode.
+    pub fn kind_of(&self, ino: u64) -> Option<EntryKind> {
+        self.reverse.get(&ino).map(|entry| entry.kind)
     }
-}
 
-impl FileInode {
-    pub fn from_number(parent: DirInode, num: u64) -> Option<Self> {
-        if num > MAX_FILES {
-            None
-        } else {
-            Some(Self(parent, num.try_into().unwrap()))
+    /// Whether `ino` is a directory (the root, or a previously allocated
+    /// `pitNNN`).
+    pub fn is_dir(&self, ino: u64) -> bool {
+        ino == ROOT_INO || matches!(self.kind_of(ino), Some(EntryKind::Dir))
+    }
+
+    /// How many levels `ino` is below the root: its `pitNNN` hops for a
+    /// directory, or one more than its parent directory for a symlink.
+    pub fn depth(&self, ino: u64) -> u32 {
+        match self.reverse.get(&ino) {
+            Some(entry) => entry.depth,
+            None => 0,
         }
     }
 
-    pub fn num(&self) -> u64 {
-        self.1.into()
+    /// The directory one level up from `ino` (the root is its own parent).
+    pub fn parent(&self, ino: u64) -> u64 {
+        match self.reverse.get(&ino) {
+            Some(entry) => entry.parent,
+            None => ROOT_INO,
+        }
     }
 }
-impl Inode {
-    pub fn from_ino_u64(ino: u64) -> Self {
-        let dir_number = (ino & 0xFFFF_FFFF) as u32;
-        let file_number = (ino >> 32) as u32;
-
-        if file_number == 0 {
-            assert!(dir_number != 0);
-            DirInode(dir_number).into()
-        } else {
-            FileInode(DirInode(dir_number), file_number).into()
-        }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_has_depth_zero_and_is_its_own_parent() {
+        let table = InodeTable::new();
+        assert_eq!(table.depth(ROOT_INO), 0);
+        assert_eq!(table.parent(ROOT_INO), ROOT_INO);
+        assert!(table.is_dir(ROOT_INO));
+    }
+
+    #[test]
+    fn lookup_or_insert_is_stable_and_increments_depth() {
+        let mut table = InodeTable::new();
+        let pit001 = table.lookup_or_insert(ROOT_INO, "pit001", EntryKind::Dir);
+        let pit001_again = table.lookup_or_insert(ROOT_INO, "pit001", EntryKind::Dir);
+        assert_eq!(pit001, pit001_again);
+        assert_eq!(table.depth(pit001), 1);
+        assert_eq!(table.parent(pit001), ROOT_INO);
+
+        let nested = table.lookup_or_insert(pit001, "pit001", EntryKind::Dir);
+        assert_ne!(nested, pit001);
+        assert_eq!(table.depth(nested), 2);
+        assert_eq!(table.parent(nested), pit001);
+    }
+
+    #[test]
+    fn distinct_names_under_the_same_parent_get_distinct_inodes() {
+        let mut table = InodeTable::new();
+        let a = table.lookup_or_insert(ROOT_INO, "pit001", EntryKind::Dir);
+        let b = table.lookup_or_insert(ROOT_INO, "pit002", EntryKind::Dir);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn kind_of_distinguishes_dirs_from_symlinks() {
+        let mut table = InodeTable::new();
+        let dir = table.lookup_or_insert(ROOT_INO, "pit001", EntryKind::Dir);
+        let link = table.lookup_or_insert(ROOT_INO, "up", EntryKind::Symlink(SymlinkKind::Up));
+        assert_eq!(table.kind_of(dir), Some(EntryKind::Dir));
+        assert!(table.is_dir(dir));
+        assert_eq!(table.kind_of(link), Some(EntryKind::Symlink(SymlinkKind::Up)));
+        assert!(!table.is_dir(link));
     }
 }
@@ -1,23 +1,29 @@
 use fuser::{
-    FileAttr, FileType, Filesystem, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry, Request,
+    FileAttr, FileType, Filesystem, ReplyAttr, ReplyData, ReplyDirectory, ReplyDirectoryPlus,
+    ReplyEntry, Request,
 };
-use libc::{EISDIR, ENOENT, ENOTDIR};
+use libc::ENOENT;
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::sync::LazyLock;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 mod inode;
-use inode::{DirInode, FileInode, Inode};
-
-use crate::fs::inode::{MAX_DIRS, MAX_FILES};
+use inode::{EntryKind, HELLO_INO, InodeTable, ROOT_INO, SymlinkKind};
 
 const TTL: Duration = Duration::from_secs(1); // 1 second
 
+/// TTL handed out by `readdirplus`. Zero, deliberately: a real TTL would let
+/// the kernel cache each child's attributes and skip the follow-up `lookup`,
+/// but we want every pass over the tree to cost the crawler a fresh lookup
+/// per entry.
+const READDIRPLUS_TTL: Duration = Duration::ZERO;
+
 static EPOCH: LazyLock<SystemTime> = LazyLock::new(|| UNIX_EPOCH + Duration::from_secs(1751364000));
 
-fn dir_attr(inode: DirInode) -> FileAttr {
+fn dir_attr(ino: u64) -> FileAttr {
     FileAttr {
-        ino: inode.into(),
+        ino,
         size: 0,
         blocks: 0,
         atime: *EPOCH,
@@ -35,13 +41,13 @@ fn dir_attr(inode: DirInode) -> FileAttr {
     }
 }
 
-const HELLO_TXT_CONTENT: &str = "Hello World!\n";
+const FILE_BLKSIZE: u32 = 512;
 
-fn file_attr(inode: FileInode) -> FileAttr {
+fn file_attr(ino: u64, size: u64) -> FileAttr {
     FileAttr {
-        ino: inode.into(),
-        size: 13,
-        blocks: 1,
+        ino,
+        size,
+        blocks: size.div_ceil(FILE_BLKSIZE as u64),
         atime: *EPOCH,
         mtime: *EPOCH,
         ctime: *EPOCH,
@@ -53,58 +59,232 @@ fn file_attr(inode: FileInode) -> FileAttr {
         gid: 20,
         rdev: 0,
         flags: 0,
-        blksize: 512,
+        blksize: FILE_BLKSIZE,
     }
 }
 
+/// Fills `len` bytes starting at `offset` in `ino`'s synthetic file with a
+/// cheap, deterministic pattern, so huge files never need to be stored.
+fn synth_file_data(ino: u64, offset: u64, len: usize) -> Vec<u8> {
+    let seed = (ino as u32) ^ 0x9E37_79B9;
+    (0..len as u64)
+        .map(|i| {
+            let index = offset.wrapping_add(i);
+            let mut x = seed ^ (index as u32) ^ (index >> 32) as u32;
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            x as u8
+        })
+        .collect()
+}
+
+/// How many bytes a read of `requested` starting at `offset` actually yields
+/// out of a file of `file_size` bytes, clamped so reads never run past EOF.
+fn clamped_len(file_size: u64, offset: u64, requested: u32) -> usize {
+    (requested as u64).min(file_size.saturating_sub(offset)) as usize
+}
+
 fn dir_name(num: u64) -> String {
     format!("pit{num:03}")
 }
 
+fn dir_name_to_num(name: &str) -> Option<u64> {
+    name.strip_prefix("pit")?.parse().ok()
+}
+
+/// Which `SymlinkKind` the name `next` or `up` should allocate, if any.
+fn symlink_name_to_kind(name: &str) -> Option<SymlinkKind> {
+    match name {
+        "next" => Some(SymlinkKind::Next),
+        "up" => Some(SymlinkKind::Up),
+        _ => None,
+    }
+}
+
+/// `size` is the length of the symlink's target text, matching what real
+/// symlinks report so tools that read `st_size` directly (rather than
+/// calling `readlink`) still see something sane.
+fn symlink_attr(ino: u64, size: u64) -> FileAttr {
+    FileAttr {
+        ino,
+        size,
+        blocks: 0,
+        atime: *EPOCH,
+        mtime: *EPOCH,
+        ctime: *EPOCH,
+        crtime: *EPOCH,
+        kind: FileType::Symlink,
+        perm: 0o777,
+        nlink: 1,
+        uid: 501,
+        gid: 20,
+        rdev: 0,
+        flags: 0,
+        blksize: 512,
+    }
+}
+
+/// Default `hello.txt` size: 4 GiB, large enough to keep a scanner busy.
+const DEFAULT_FILE_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Default read throttle: 64 KiB/s.
+const DEFAULT_READ_RATE: u64 = 64 * 1024;
+
+/// Token-bucket parameters for the per-client request throttle.
+#[derive(Debug, Clone, Copy)]
+struct ThrottleConfig {
+    /// Tokens refilled per second.
+    rate: f64,
+    /// Maximum tokens a client can bank.
+    burst: f64,
+    /// Sleep imposed on the first request once a client runs dry.
+    base_delay: Duration,
+    /// Ceiling on the exponential backoff below.
+    max_delay: Duration,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            rate: 5.0,
+            burst: 10.0,
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Per-client token bucket, keyed by uid in `TarpitFs::clients`.
+#[derive(Debug)]
+struct ClientState {
+    tokens: f64,
+    last_refill: Instant,
+    /// How many consecutive requests have found the bucket empty; drives
+    /// the exponential backoff and decays once the client settles down.
+    empty_streak: u32,
+}
+
+impl ClientState {
+    fn new(cfg: &ThrottleConfig, now: Instant) -> Self {
+        Self {
+            tokens: cfg.burst,
+            last_refill: now,
+            empty_streak: 0,
+        }
+    }
+
+    /// Consumes one token, returning how long the caller should sleep.
+    fn throttle(&mut self, cfg: &ThrottleConfig) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * cfg.rate).min(cfg.burst);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            self.empty_streak = self.empty_streak.saturating_sub(1);
+            return Duration::ZERO;
+        }
+
+        let backoff = cfg.base_delay.saturating_mul(1u32 << self.empty_streak.min(16));
+        self.empty_streak += 1;
+        backoff.min(cfg.max_delay)
+    }
+}
+
 pub struct TarpitBuilder {
     num_dirs: u64,
-    num_files: u64,
+    max_depth: Option<u64>,
+    symlink_loops: bool,
+    file_size: u64,
+    read_rate: u64,
+    throttle: ThrottleConfig,
 }
 
 impl Default for TarpitBuilder {
     fn default() -> Self {
         Self {
             num_dirs: 10,
-            num_files: 10,
+            max_depth: None,
+            symlink_loops: false,
+            file_size: DEFAULT_FILE_SIZE,
+            read_rate: DEFAULT_READ_RATE,
+            throttle: ThrottleConfig::default(),
         }
     }
 }
 
 impl TarpitBuilder {
-    /// Set the number of directories.
+    /// Set the number of subdirectories created at every level of the tree.
     pub fn dirs(mut self, num_dirs: u64) -> Self {
-        if num_dirs > MAX_DIRS {
-            panic!("number of directories is too large");
-        }
         self.num_dirs = num_dirs;
         self
     }
 
-    /// Set the number of files per directory.
-    pub fn files(mut self, num_files: u64) -> Self {
-        if num_files > MAX_FILES {
-            panic!("number of files is too large");
-        }
-        self.num_files = num_files;
+    /// Cap how many `pitNNN` levels deep the tree may recurse. The default
+    /// is unbounded, so a naive recursive crawler never reaches bottom.
+    pub fn max_depth(mut self, max_depth: u64) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Plant `next`/`up` symlinks in every directory that chain and loop,
+    /// defeating crawlers that follow links instead of just recursing.
+    pub fn symlink_loops(mut self, symlink_loops: bool) -> Self {
+        self.symlink_loops = symlink_loops;
+        self
+    }
+
+    /// Set the reported size of `hello.txt`, synthesized on the fly when read.
+    pub fn file_size(mut self, file_size: u64) -> Self {
+        self.file_size = file_size;
+        self
+    }
+
+    /// Cap how fast `hello.txt` can be read back, in bytes per second.
+    pub fn read_rate(mut self, bytes_per_second: u64) -> Self {
+        self.read_rate = bytes_per_second;
+        self
+    }
+
+    /// Configure the per-client token bucket that replaces the flat
+    /// per-`readdir` sleep: clients get `burst` free requests, refilling at
+    /// `rate` per second, and once a client drains its bucket it sleeps
+    /// `base_delay * 2^consecutive_empty_hits`, capped at `max_delay`.
+    pub fn throttle(mut self, rate: f64, burst: f64, base_delay: Duration, max_delay: Duration) -> Self {
+        self.throttle = ThrottleConfig {
+            rate,
+            burst,
+            base_delay,
+            max_delay,
+        };
         self
     }
 
     pub fn build(self) -> TarpitFs {
         TarpitFs {
             num_dirs: self.num_dirs,
-            num_files: self.num_files,
+            max_depth: self.max_depth,
+            symlink_loops: self.symlink_loops,
+            file_size: self.file_size,
+            read_rate: self.read_rate,
+            throttle: self.throttle,
+            clients: HashMap::new(),
+            dirs: InodeTable::new(),
         }
     }
 }
 
 pub struct TarpitFs {
     num_dirs: u64,
-    num_files: u64,
+    max_depth: Option<u64>,
+    symlink_loops: bool,
+    file_size: u64,
+    read_rate: u64,
+    throttle: ThrottleConfig,
+    clients: HashMap<u32, ClientState>,
+    dirs: InodeTable,
 }
 
 impl TarpitFs {
@@ -112,173 +292,202 @@ impl TarpitFs {
         TarpitBuilder::default()
     }
 
-    fn dir_name_to_inode(&self, name: &str) -> Option<DirInode> {
-        let num: u64 = name.strip_prefix("pit")?.parse().ok()?;
-        self.dir_num_to_inode(num)
+    /// Whether `ino` is still shallow enough to grow further `pitNNN`
+    /// children, given the configured `max_depth`.
+    fn allows_recursion(&self, ino: u64) -> bool {
+        match self.max_depth {
+            Some(max_depth) => self.dirs.depth(ino) < max_depth as u32,
+            None => true,
+        }
     }
 
-    fn dir_num_to_inode(&self, num: u64) -> Option<DirInode> {
-        if num <= self.num_dirs {
-            // inode 1 is used by the mount point.
-            DirInode::from_number(num + 1)
-        } else {
-            None
+    /// The generated target of a `next`/`up` symlink. The targets are
+    /// procedurally chosen to form cycles, not to point at real inodes.
+    fn symlink_target(&self, ino: u64) -> Option<String> {
+        match self.dirs.kind_of(ino)? {
+            EntryKind::Symlink(SymlinkKind::Next) => {
+                let sibling = 1 + (ino % self.num_dirs.max(1));
+                Some(format!("../{}", dir_name(sibling)))
+            }
+            EntryKind::Symlink(SymlinkKind::Up) => Some("..".to_string()),
+            EntryKind::Dir => None,
         }
     }
 
-    /// returns (inode, type, name)
-    fn dir_num_to_dirent(&self, num: u64) -> (DirInode, FileType, String) {
-        let ino = self.dir_num_to_inode(num).unwrap();
-        (ino, FileType::Directory, dir_name(num))
-    }
+    /// Builds the full entry listing for directory `ino`: `.`, `..`,
+    /// `hello.txt`, the `next`/`up` symlinks if enabled, and the `num_dirs`
+    /// subdirectories if recursion hasn't hit `max_depth`. Shared by
+    /// `readdir` and `readdirplus` so the two never drift apart.
+    fn dir_entries(&mut self, ino: u64) -> Vec<(u64, FileType, String)> {
+        let mut entries = Vec::with_capacity(self.num_dirs as usize + 5);
+        entries.extend([
+            (ino, FileType::Directory, ".".to_string()),
+            (self.dirs.parent(ino), FileType::Directory, "..".to_string()),
+            (HELLO_INO, FileType::RegularFile, "hello.txt".to_string()),
+        ]);
+
+        if self.symlink_loops {
+            let next_ino = self
+                .dirs
+                .lookup_or_insert(ino, "next", EntryKind::Symlink(SymlinkKind::Next));
+            let up_ino = self
+                .dirs
+                .lookup_or_insert(ino, "up", EntryKind::Symlink(SymlinkKind::Up));
+            entries.extend([
+                (next_ino, FileType::Symlink, "next".to_string()),
+                (up_ino, FileType::Symlink, "up".to_string()),
+            ]);
+        }
 
-    fn inode_to_dir(&self, ino: u64) -> Option<DirInode> {
-        match Inode::from_ino_u64(ino) {
-            Inode::File(_) => None,
-            Inode::Dir(dir_inode) => {
-                if dir_inode.num() > self.num_dirs + 1 {
-                    None
-                } else {
-                    Some(dir_inode)
-                }
+        // Every directory looks identical, so the tree never bottoms out:
+        // always offer the same `num_dirs` subdirectories regardless of how
+        // deep we already are, up to `max_depth` if one was configured.
+        if self.allows_recursion(ino) {
+            for num in 1..=self.num_dirs {
+                let name = dir_name(num);
+                let child_ino = self.dirs.lookup_or_insert(ino, &name, EntryKind::Dir);
+                entries.push((child_ino, FileType::Directory, name));
             }
         }
+
+        entries
     }
 
-    fn inode_attr(&self, inode: Inode) -> Option<FileAttr> {
-        match inode {
-            Inode::Dir(dir_inode) => {
-                (dir_inode.num() <= self.num_dirs).then_some(dir_attr(dir_inode))
-            }
-            Inode::File(file_inode) => {
-                (file_inode.num() <= self.num_files).then_some(file_attr(file_inode))
-            }
+    /// The `FileAttr` for a `dir_entries` entry, given its inode and kind.
+    fn entry_attr(&self, ino: u64, kind: FileType) -> FileAttr {
+        match kind {
+            FileType::Directory => dir_attr(ino),
+            FileType::RegularFile => file_attr(ino, self.file_size),
+            FileType::Symlink => symlink_attr(ino, self.symlink_target(ino).map_or(0, |t| t.len() as u64)),
+            _ => unreachable!("tarpit only ever serves directories, hello.txt, and symlinks"),
+        }
+    }
+
+    /// Consumes one token from `uid`'s bucket and sleeps however long that
+    /// client has earned: nothing while well-behaved, an exponentially
+    /// growing delay once it's hammering the mount.
+    fn throttle_client(&mut self, uid: u32) {
+        let state = self
+            .clients
+            .entry(uid)
+            .or_insert_with(|| ClientState::new(&self.throttle, Instant::now()));
+        let delay = state.throttle(&self.throttle);
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
         }
     }
 }
 
 impl Filesystem for TarpitFs {
-    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
-        #![allow(
-            clippy::collapsible_if,
-            reason = "right style for adding more functionality later"
-        )]
-
+    fn lookup(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
         let Some(name) = name.to_str() else {
             return reply.error(ENOENT);
         };
 
         log::info!("lookup {parent:0x} {name:?}");
 
-        // Looking at a directory entry from the top dir.
-        if parent == 1 {
-            match self.dir_name_to_inode(name) {
-                Some(inode) => {
-                    let attr = dir_attr(inode);
-                    return reply.entry(&TTL, &attr, 0);
-                }
-                None => {
-                    log::error!("no inode found in top dir");
-                    return reply.error(ENOENT);
-                }
-            }
-        }
+        self.throttle_client(req.uid());
 
-        // Looking at a file from a directory.
-        let parent_inode = Inode::from_ino_u64(parent);
-        let Inode::Dir(parent_inode) = parent_inode else {
-            log::error!("parent inode {parent:0x} not a directory");
-            return reply.error(ENOENT);
-        };
-        if parent_inode.num() > self.num_dirs + 1 {
-            log::error!("parent directory num out of range");
+        if !self.dirs.is_dir(parent) {
+            log::error!("parent inode {parent:0x} not known");
             return reply.error(ENOENT);
         }
+
         if name == "hello.txt" {
-            let file = FileInode::from_number(parent_inode, 2).unwrap();
-            return reply.entry(&TTL, &file_attr(file), 0);
+            return reply.entry(&TTL, &file_attr(HELLO_INO, self.file_size), 0);
+        }
+
+        if self.symlink_loops {
+            if let Some(kind) = symlink_name_to_kind(name) {
+                let ino = self
+                    .dirs
+                    .lookup_or_insert(parent, name, EntryKind::Symlink(kind));
+                let size = self.symlink_target(ino).map_or(0, |t| t.len() as u64);
+                return reply.entry(&TTL, &symlink_attr(ino, size), 0);
+            }
+        }
+
+        if let Some(num) = dir_name_to_num(name) {
+            if num >= 1 && num <= self.num_dirs && self.allows_recursion(parent) {
+                let ino = self.dirs.lookup_or_insert(parent, name, EntryKind::Dir);
+                return reply.entry(&TTL, &dir_attr(ino), 0);
+            }
         }
 
+        log::error!("no inode found in {parent:0x} for {name:?}");
         reply.error(ENOENT);
     }
 
     fn getattr(&mut self, _req: &Request, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
-        let inode = Inode::from_ino_u64(ino);
-        match self.inode_attr(inode) {
-            Some(attr) => {
-                return reply.attr(&TTL, &attr);
-            }
-            None => {
-                return reply.error(ENOENT);
+        if ino == HELLO_INO {
+            return reply.attr(&TTL, &file_attr(ino, self.file_size));
+        }
+
+        if ino == ROOT_INO {
+            return reply.attr(&TTL, &dir_attr(ino));
+        }
+
+        match self.dirs.kind_of(ino) {
+            Some(EntryKind::Dir) => reply.attr(&TTL, &dir_attr(ino)),
+            Some(EntryKind::Symlink(_)) => {
+                let size = self.symlink_target(ino).map_or(0, |t| t.len() as u64);
+                reply.attr(&TTL, &symlink_attr(ino, size))
             }
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn readlink(&mut self, _req: &Request, ino: u64, reply: ReplyData) {
+        match self.symlink_target(ino) {
+            Some(target) => reply.data(target.as_bytes()),
+            None => reply.error(ENOENT),
         }
     }
 
     fn read(
         &mut self,
-        _req: &Request,
+        req: &Request,
         ino: u64,
         _fh: u64,
         offset: i64,
-        _size: u32,
+        size: u32,
         _flags: i32,
         _lock: Option<u64>,
         reply: ReplyData,
     ) {
-        match Inode::from_ino_u64(ino) {
-            Inode::Dir(_) => {
-                reply.error(EISDIR);
-            }
-            Inode::File(file_inode) => {
-                if file_inode.num() == 2 {
-                    reply.data(&HELLO_TXT_CONTENT.as_bytes()[offset as usize..]);
-                } else {
-                    reply.error(ENOENT);
-                }
-            }
+        self.throttle_client(req.uid());
+
+        if ino != HELLO_INO {
+            return reply.error(ENOENT);
+        }
+
+        let offset = offset as u64;
+        let len = clamped_len(self.file_size, offset, size);
+        let data = synth_file_data(ino, offset, len);
+
+        // Trickle the download out at `read_rate` bytes per second.
+        if self.read_rate > 0 {
+            std::thread::sleep(Duration::from_secs_f64(len as f64 / self.read_rate as f64));
         }
+
+        reply.data(&data);
     }
 
     fn readdir(
         &mut self,
-        _req: &Request,
+        req: &Request,
         ino: u64,
         _fh: u64,
         offset: i64,
         mut reply: ReplyDirectory,
     ) {
-        let Inode::Dir(dir_inode) = Inode::from_ino_u64(ino) else {
-            return reply.error(ENOTDIR);
-        };
-        let dir_num = dir_inode.num();
+        self.throttle_client(req.uid());
 
-        let mut entries = Vec::new();
-
-        if dir_num == 1 {
-            entries.reserve(2 + self.num_dirs as usize);
-            entries.extend([
-                (1, FileType::Directory, ".".to_string()),
-                (1, FileType::Directory, "..".to_string()),
-            ]);
-            let subdirs = (1..self.num_dirs + 1).map(|dir_num| {
-                let (dir, ty, name) = self.dir_num_to_dirent(dir_num);
-                (dir.into(), ty, name)
-            });
-            entries.extend(subdirs);
-        } else if dir_num <= self.num_dirs + 1 {
-            let file_ino: u64 = FileInode::from_number(dir_inode, 2).unwrap().into();
-            entries.extend([
-                (ino, FileType::Directory, ".".to_string()),
-                (1, FileType::Directory, "..".to_string()),
-                (file_ino, FileType::RegularFile, "hello.txt".to_string()),
-            ]);
-        } else {
+        if !self.dirs.is_dir(ino) {
             return reply.error(ENOENT);
-        };
-
-        // Deliberate slowdown
-        std::thread::sleep(Duration::from_millis(50));
+        }
 
+        let entries = self.dir_entries(ino);
         for (i, entry) in entries.into_iter().enumerate().skip(offset as usize) {
             // i + 1 means the index of the next entry
             if reply.add(entry.0, (i + 1) as i64, entry.1, entry.2) {
@@ -287,4 +496,243 @@ impl Filesystem for TarpitFs {
         }
         reply.ok();
     }
+
+    fn readdirplus(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectoryPlus,
+    ) {
+        self.throttle_client(req.uid());
+
+        if !self.dirs.is_dir(ino) {
+            return reply.error(ENOENT);
+        }
+
+        let entries = self.dir_entries(ino);
+        for (i, (child_ino, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            let attr = self.entry_attr(child_ino, kind);
+            // i + 1 means the index of the next entry
+            if reply.add(child_ino, (i + 1) as i64, name, &READDIRPLUS_TTL, &attr, 0) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recursion_is_unbounded_by_default() {
+        let mut fs = TarpitFs::builder().dirs(2).build();
+        let mut ino = ROOT_INO;
+        for _ in 0..50 {
+            assert!(fs.allows_recursion(ino));
+            ino = fs.dirs.lookup_or_insert(ino, "pit001", EntryKind::Dir);
+        }
+    }
+
+    #[test]
+    fn max_depth_stops_recursion_at_the_configured_level() {
+        let mut fs = TarpitFs::builder().dirs(2).max_depth(2).build();
+
+        let depth1 = fs.dirs.lookup_or_insert(ROOT_INO, "pit001", EntryKind::Dir);
+        let depth2 = fs.dirs.lookup_or_insert(depth1, "pit001", EntryKind::Dir);
+
+        assert!(fs.allows_recursion(ROOT_INO));
+        assert!(fs.allows_recursion(depth1));
+        assert!(!fs.allows_recursion(depth2));
+
+        // A directory at the depth limit still lists hello.txt, but no
+        // further pitNNN subdirectories.
+        let names: Vec<_> = fs
+            .dir_entries(depth2)
+            .into_iter()
+            .map(|(_, _, name)| name)
+            .collect();
+        assert!(names.contains(&"hello.txt".to_string()));
+        assert!(!names.iter().any(|name| name.starts_with("pit")));
+    }
+
+    #[test]
+    fn up_symlink_targets_the_parent_directory() {
+        let mut fs = TarpitFs::builder().dirs(2).symlink_loops(true).build();
+        let pit001 = fs.dirs.lookup_or_insert(ROOT_INO, "pit001", EntryKind::Dir);
+        let up = fs
+            .dirs
+            .lookup_or_insert(pit001, "up", EntryKind::Symlink(SymlinkKind::Up));
+        assert_eq!(fs.symlink_target(up).as_deref(), Some(".."));
+    }
+
+    #[test]
+    fn symlink_attr_size_matches_the_target_text_length() {
+        let mut fs = TarpitFs::builder().dirs(2).symlink_loops(true).build();
+        let pit001 = fs.dirs.lookup_or_insert(ROOT_INO, "pit001", EntryKind::Dir);
+        let up = fs
+            .dirs
+            .lookup_or_insert(pit001, "up", EntryKind::Symlink(SymlinkKind::Up));
+        let attr = fs.entry_attr(up, FileType::Symlink);
+        assert_eq!(attr.size, fs.symlink_target(up).unwrap().len() as u64);
+    }
+
+    #[test]
+    fn next_symlink_targets_a_sibling_pit_directory() {
+        let mut fs = TarpitFs::builder().dirs(5).symlink_loops(true).build();
+        let pit001 = fs.dirs.lookup_or_insert(ROOT_INO, "pit001", EntryKind::Dir);
+        let next = fs
+            .dirs
+            .lookup_or_insert(pit001, "next", EntryKind::Symlink(SymlinkKind::Next));
+        let target = fs.symlink_target(next).unwrap();
+        assert!(target.starts_with("../pit"));
+    }
+
+    #[test]
+    fn a_plain_directory_has_no_symlink_target() {
+        let mut fs = TarpitFs::builder().dirs(2).build();
+        let pit001 = fs.dirs.lookup_or_insert(ROOT_INO, "pit001", EntryKind::Dir);
+        assert_eq!(fs.symlink_target(pit001), None);
+    }
+
+    #[test]
+    fn readdir_entries_only_include_next_up_when_enabled() {
+        let mut plain = TarpitFs::builder().dirs(1).build();
+        let plain_names: Vec<_> = plain
+            .dir_entries(ROOT_INO)
+            .into_iter()
+            .map(|(_, _, name)| name)
+            .collect();
+        assert!(!plain_names.contains(&"next".to_string()));
+        assert!(!plain_names.contains(&"up".to_string()));
+
+        let mut looping = TarpitFs::builder().dirs(1).symlink_loops(true).build();
+        let looping_names: Vec<_> = looping
+            .dir_entries(ROOT_INO)
+            .into_iter()
+            .map(|(_, _, name)| name)
+            .collect();
+        assert!(looping_names.contains(&"next".to_string()));
+        assert!(looping_names.contains(&"up".to_string()));
+    }
+
+    #[test]
+    fn synth_file_data_is_deterministic_and_position_dependent() {
+        let a = synth_file_data(HELLO_INO, 0, 16);
+        let b = synth_file_data(HELLO_INO, 0, 16);
+        assert_eq!(a, b);
+
+        let c = synth_file_data(HELLO_INO, 16, 16);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn synth_file_data_at_an_offset_matches_the_tail_of_a_longer_read() {
+        let whole = synth_file_data(HELLO_INO, 0, 32);
+        let tail = synth_file_data(HELLO_INO, 16, 16);
+        assert_eq!(&whole[16..], &tail[..]);
+    }
+
+    #[test]
+    fn file_attr_reports_the_configured_size_and_block_count() {
+        let attr = file_attr(HELLO_INO, 5 * 1024 * 1024 * 1024);
+        assert_eq!(attr.size, 5 * 1024 * 1024 * 1024);
+        assert_eq!(attr.blocks, attr.size.div_ceil(FILE_BLKSIZE as u64));
+        assert_eq!(attr.kind, FileType::RegularFile);
+    }
+
+    #[test]
+    fn read_length_is_clamped_to_the_remaining_file_size() {
+        assert_eq!(clamped_len(10, 4, 100), 6);
+        assert_eq!(clamped_len(10, 10, 100), 0);
+        assert_eq!(clamped_len(10, 20, 100), 0);
+        assert_eq!(clamped_len(10, 0, 3), 3);
+    }
+
+    #[test]
+    fn burst_requests_are_free_until_the_bucket_runs_dry() {
+        let cfg = ThrottleConfig {
+            rate: 1.0,
+            burst: 3.0,
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(5),
+        };
+        let mut state = ClientState::new(&cfg, Instant::now());
+
+        for _ in 0..3 {
+            assert_eq!(state.throttle(&cfg), Duration::ZERO);
+        }
+        assert!(state.throttle(&cfg) > Duration::ZERO);
+    }
+
+    #[test]
+    fn backoff_escalates_and_is_capped_at_max_delay() {
+        let cfg = ThrottleConfig {
+            rate: 0.0,
+            burst: 0.0,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(100),
+        };
+        let mut state = ClientState::new(&cfg, Instant::now());
+
+        let first = state.throttle(&cfg);
+        let second = state.throttle(&cfg);
+        assert!(second > first);
+
+        for _ in 0..10 {
+            state.throttle(&cfg);
+        }
+        assert_eq!(state.throttle(&cfg), cfg.max_delay);
+    }
+
+    #[test]
+    fn empty_streak_decays_once_tokens_are_available_again() {
+        let cfg = ThrottleConfig {
+            rate: 0.0,
+            burst: 0.0,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(5),
+        };
+        let mut state = ClientState::new(&cfg, Instant::now());
+        state.throttle(&cfg);
+        state.throttle(&cfg);
+        assert!(state.empty_streak >= 2);
+
+        state.tokens = 10.0;
+        state.throttle(&cfg);
+        assert!(state.empty_streak < 2);
+    }
+
+    #[test]
+    fn entry_attr_dispatches_to_the_right_attr_kind() {
+        // readdirplus hands out READDIRPLUS_TTL rather than TTL for exactly
+        // these attrs, so every entry forces a fresh lookup next pass; that
+        // only matters if entry_attr's dispatch is actually correct per kind.
+        assert_eq!(READDIRPLUS_TTL, Duration::ZERO);
+
+        let mut fs = TarpitFs::builder().dirs(2).symlink_loops(true).build();
+        let pit001 = fs.dirs.lookup_or_insert(ROOT_INO, "pit001", EntryKind::Dir);
+        let up = fs
+            .dirs
+            .lookup_or_insert(pit001, "up", EntryKind::Symlink(SymlinkKind::Up));
+
+        assert_eq!(fs.entry_attr(pit001, FileType::Directory).kind, FileType::Directory);
+        assert_eq!(fs.entry_attr(up, FileType::Symlink).kind, FileType::Symlink);
+        let file = fs.entry_attr(HELLO_INO, FileType::RegularFile);
+        assert_eq!(file.kind, FileType::RegularFile);
+        assert_eq!(file.size, fs.file_size);
+    }
+
+    #[test]
+    fn dir_entries_attrs_agree_with_what_readdirplus_would_report() {
+        let mut fs = TarpitFs::builder().dirs(2).symlink_loops(true).build();
+        for (ino, kind, name) in fs.dir_entries(ROOT_INO) {
+            let attr = fs.entry_attr(ino, kind);
+            assert_eq!(attr.ino, ino, "attr for {name:?} reported the wrong inode");
+            assert_eq!(attr.kind, kind, "attr for {name:?} reported the wrong kind");
+        }
+    }
 }